@@ -1,15 +1,35 @@
+// Cargo.toml dependencies (added since last pass - no manifest checked in):
+// rsa = "0.9"
+// sha1 = "0.10"
+// aes = "0.8"
+// rand = "0.8"
+// reqwest = { version = "0.11", features = ["json"] }
+
 use anyhow::{anyhow, Result};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead, Write};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
-use tokio::sync::Semaphore;
+use tokio::net::UdpSocket;
+use tokio::sync::{mpsc, Semaphore};
 use tokio::time::timeout;
 use flate2::read::ZlibDecoder;
 use std::io::Read;
+use aes::cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit};
+use aes::Aes128;
+use rand::RngCore;
+use rsa::pkcs8::DecodePublicKey;
+use rsa::{Pkcs1v15Encrypt, RsaPublicKey};
+use sha1::{Digest, Sha1};
+
+mod codec;
+use codec::Serializable;
 
 // Timeout configs - feel free to adjust these
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
@@ -20,12 +40,21 @@ const PROTOCOL_VERSION: i32 = 763; // 1.20.1
 const MAX_PROTOCOL_VERSION: i32 = 800;
 const MIN_PROTOCOL_VERSION: i32 = 47; // Anything older than 1.8 is pretty rare
 
+// Every completed result is appended here as it streams in, one JSON object
+// per line, instead of piling up in memory until the run finishes. This is
+// also what --resume reads back on startup to skip already-scanned targets.
+const RESULTS_PATH: &str = "results.ndjson";
+
 #[derive(Debug, Serialize, Deserialize)]
 struct ScanResult {
     ip: String,
     port: u16,
     #[serde(skip_serializing_if = "Option::is_none")]
     motd: Option<String>,
+    // ANSI-rendered MOTD for the live progress display - not part of the
+    // on-disk report, which stays plain text for other tooling to consume
+    #[serde(skip)]
+    motd_ansi: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     version: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -39,7 +68,11 @@ struct ScanResult {
     #[serde(skip_serializing_if = "Option::is_none")]
     favicon: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    auth_mode: Option<i32>, // -1=unknown, 0=cracked, 1=premium, 2=whitelisted
+    auth_mode: Option<i32>, // -1=unknown, 0=cracked, 1=premium, 2=whitelisted, 3=encryption negotiated but join rejected
+    // Only populated when the UDP query stage runs and the server has
+    // enable-query on - the status ping has no equivalent field
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plugins: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
 }
@@ -47,7 +80,10 @@ struct ScanResult {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct Player {
     name: String,
-    uuid: String,
+    // The status ping sample always has one; the UDP query player list is
+    // just names, so query-sourced entries leave this as None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uuid: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -82,21 +118,316 @@ struct PlayerSample {
     id: String,
 }
 
-// VarInt stuff - standard MC protocol encoding
-fn encode_varint(mut val: i32) -> Vec<u8> {
-    let mut buf = Vec::new();
-    loop {
-        let mut byte = (val & 0x7F) as u8;
-        val >>= 7;
-        if val != 0 {
-            byte |= 0x80;
+// Output format for the final report - lets other tooling consume scan results
+// instead of scraping the box-drawn terminal output
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReportFormat {
+    Text,
+    Json,
+    Ndjson,
+}
+
+impl std::str::FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(ReportFormat::Text),
+            "json" => Ok(ReportFormat::Json),
+            "ndjson" => Ok(ReportFormat::Ndjson),
+            other => Err(format!("unknown format '{}' (expected text, json, or ndjson)", other)),
         }
-        buf.push(byte);
-        if val == 0 {
-            break;
+    }
+}
+
+struct CliArgs {
+    output: Option<String>,
+    format: ReportFormat,
+    fix: bool,
+    resume: bool,
+    check_auth: bool,
+    check_query: bool,
+}
+
+fn parse_args() -> Result<CliArgs> {
+    let mut output = None;
+    let mut format = ReportFormat::Text;
+    let mut fix = false;
+    let mut resume = false;
+    let mut check_auth = false;
+    let mut check_query = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" | "-o" => {
+                output = Some(args.next().ok_or_else(|| anyhow!("--output needs a file path"))?);
+            }
+            "--format" | "-f" => {
+                let raw = args.next().ok_or_else(|| anyhow!("--format needs a value"))?;
+                format = raw.parse().map_err(|e: String| anyhow!(e))?;
+            }
+            "--fix" => fix = true,
+            "--resume" => resume = true,
+            "--check-auth" => check_auth = true,
+            "--check-query" => check_query = true,
+            other => return Err(anyhow!("unrecognized argument: {}", other)),
+        }
+    }
+
+    Ok(CliArgs { output, format, fix, resume, check_auth, check_query })
+}
+
+// scope note: repurposed for input.txt targets, not MCP manifests - see PR description
+
+// A problem spotted in an input.txt target line, plus how we'd repair it.
+// `--fix` only writes a repaired line back once diagnosing the repaired
+// version comes back clean - otherwise it's left for the operator.
+struct TargetDiagnostic {
+    line_no: usize,
+    original: String,
+    issue: String,
+    suggested: String,
+}
+
+// Checks a single target line for the kinds of typos that currently get
+// silently papered over by `.unwrap_or(25565)` in the scan loop, hiding a
+// likely mistake instead of surfacing it.
+fn diagnose_target_line(line: &str) -> Option<String> {
+    if let Some(stripped) = line.split("://").nth(1) {
+        return Some(format!("strip scheme prefix -> {}", stripped));
+    }
+
+    if let Some((host, port_str)) = line.split_once(':') {
+        if host.is_empty() {
+            return Some("missing host -> needs manual fix".to_string());
+        }
+        match port_str.parse::<u16>() {
+            Ok(0) => Some(format!("port 0 is not valid -> {}:25565", host)),
+            Ok(_) => None,
+            Err(_) => Some(format!("port '{}' is not a u16 -> {}:25565", port_str, host)),
+        }
+    } else {
+        None
+    }
+}
+
+fn suggested_fix_for(line: &str) -> String {
+    if let Some(stripped) = line.split("://").nth(1) {
+        return stripped.to_string();
+    }
+
+    if let Some((host, port_str)) = line.split_once(':') {
+        match port_str.parse::<u16>() {
+            Ok(p) if p != 0 => format!("{}:{}", host, p),
+            _ => format!("{}:25565", host),
+        }
+    } else {
+        line.to_string()
+    }
+}
+
+// Splits an `ip:port` target line, defaulting to the vanilla Java port when
+// none is given - the one spot both the scan loop and --resume's filtering
+// need to agree on what a target's key is.
+fn parse_target(line: &str) -> (String, u16) {
+    match line.split_once(':') {
+        Some((h, p)) => (h.to_string(), p.parse().unwrap_or(25565)),
+        None => (line.to_string(), 25565),
+    }
+}
+
+struct FixReport {
+    diagnostics: Vec<TargetDiagnostic>,
+    applied: usize,
+    manual: usize,
+}
+
+// Proposes a fix for every diagnosed line and only keeps it if re-diagnosing
+// the fixed line comes back clean - refusing to apply anything that would
+// still be broken (or newly broken) afterwards.
+fn fix_targets(lines: &[String]) -> (Vec<String>, FixReport) {
+    let mut fixed_lines = Vec::with_capacity(lines.len());
+    let mut diagnostics = Vec::new();
+    let mut applied = 0;
+    let mut manual = 0;
+
+    for (i, line) in lines.iter().enumerate() {
+        match diagnose_target_line(line) {
+            None => fixed_lines.push(line.clone()),
+            Some(issue) => {
+                let suggested = suggested_fix_for(line);
+                if diagnose_target_line(&suggested).is_none() {
+                    applied += 1;
+                    fixed_lines.push(suggested.clone());
+                } else {
+                    manual += 1;
+                    fixed_lines.push(line.clone());
+                }
+                diagnostics.push(TargetDiagnostic {
+                    line_no: i + 1,
+                    original: line.clone(),
+                    issue,
+                    suggested,
+                });
+            }
+        }
+    }
+
+    (fixed_lines, FixReport { diagnostics, applied, manual })
+}
+
+// Running counts updated as each ScanResult streams through the writer
+// task, instead of being computed after the fact from an in-memory Vec -
+// that Vec is exactly what made a huge scan's memory grow unbounded.
+#[derive(Debug, Default)]
+struct ScanTally {
+    total: usize,
+    successful: usize,
+    failed: usize,
+    online_mode: usize,
+    offline_mode: usize,
+    whitelist: usize,
+    join_rejected: usize,
+}
+
+impl ScanTally {
+    fn record(&mut self, result: &ScanResult) {
+        self.total += 1;
+        if result.error.is_none() {
+            self.successful += 1;
+        } else {
+            self.failed += 1;
+        }
+        match result.auth_mode {
+            Some(0) => self.offline_mode += 1,
+            Some(1) => self.online_mode += 1,
+            Some(2) => self.whitelist += 1,
+            Some(3) => self.join_rejected += 1,
+            _ => {}
+        }
+    }
+}
+
+// Everything the final report needs - serializable so the json renderer and
+// the text renderer read from the same source of truth. The ndjson renderer
+// does not use it: it only copies the per-server lines. The per-server
+// detail lives in RESULTS_PATH on disk, not in here.
+#[derive(Debug, Serialize)]
+struct ScanReport {
+    total: usize,
+    successful: usize,
+    failed: usize,
+    check_auth: bool,
+    online_mode: usize,
+    offline_mode: usize,
+    whitelist: usize,
+    join_rejected: usize,
+    fixable: usize,
+    fixed: usize,
+    needs_manual_fix: usize,
+}
+
+impl ScanReport {
+    fn new(tally: ScanTally, check_auth: bool, fix_report: &FixReport) -> Self {
+        ScanReport {
+            total: tally.total,
+            successful: tally.successful,
+            failed: tally.failed,
+            check_auth,
+            online_mode: tally.online_mode,
+            offline_mode: tally.offline_mode,
+            whitelist: tally.whitelist,
+            join_rejected: tally.join_rejected,
+            fixable: fix_report.diagnostics.len(),
+            fixed: fix_report.applied,
+            needs_manual_fix: fix_report.manual,
+        }
+    }
+}
+
+fn render_text(report: &ScanReport, sink: &mut dyn Write) -> Result<()> {
+    writeln!(sink, "â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”")?;
+    writeln!(sink, "ğŸ“ˆ Results:")?;
+    writeln!(sink, "   Total:      {}", report.total)?;
+    writeln!(
+        sink,
+        "   âœ“ Success:  {} ({:.1}%)",
+        report.successful,
+        (report.successful as f32 / report.total as f32) * 100.0
+    )?;
+    writeln!(
+        sink,
+        "   âœ— Failed:   {} ({:.1}%)",
+        report.failed,
+        (report.failed as f32 / report.total as f32) * 100.0
+    )?;
+
+    if report.check_auth {
+        writeln!(sink)?;
+        writeln!(sink, "ğŸ” Auth:")?;
+        writeln!(sink, "   ğŸŸ¢ Online:    {}", report.online_mode)?;
+        writeln!(sink, "   ğŸŸ¡ Cracked:   {}", report.offline_mode)?;
+        writeln!(sink, "   ğŸ”´ Whitelist: {}", report.whitelist)?;
+        writeln!(sink, "   âš« Join rejected: {}", report.join_rejected)?;
+    }
+
+    if report.fixable > 0 {
+        writeln!(sink)?;
+        writeln!(sink, "ğŸ”§ Target fixes:")?;
+        writeln!(sink, "   Found:   {}", report.fixable)?;
+        writeln!(sink, "   Applied: {}", report.fixed)?;
+        writeln!(sink, "   Manual:  {}", report.needs_manual_fix)?;
+    }
+    writeln!(sink, "â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”")?;
+    Ok(())
+}
+
+// Reads the per-server detail back from RESULTS_PATH rather than keeping it
+// in memory for the whole scan - this only runs once, after the scan loop
+// has already finished, so the bounded cost here is fine.
+fn read_ndjson_results(path: &str) -> Result<Vec<serde_json::Value>> {
+    let file = File::open(path)?;
+    let mut out = Vec::new();
+    for line in io::BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
         }
+        out.push(serde_json::from_str(&line)?);
+    }
+    Ok(out)
+}
+
+fn render_json(report: &ScanReport, results_path: &str, sink: &mut dyn Write) -> Result<()> {
+    let results = read_ndjson_results(results_path)?;
+    let mut value = serde_json::to_value(report)?;
+    if let serde_json::Value::Object(ref mut map) = value {
+        map.insert("results".to_string(), serde_json::Value::Array(results));
+    }
+    serde_json::to_writer_pretty(sink, &value)?;
+    Ok(())
+}
+
+fn render_ndjson(_report: &ScanReport, results_path: &str, sink: &mut dyn Write) -> Result<()> {
+    let file = File::open(results_path)?;
+    for line in io::BufReader::new(file).lines() {
+        writeln!(sink, "{}", line?)?;
+    }
+    Ok(())
+}
+
+fn render_report(report: &ScanReport, args: &CliArgs, results_path: &str) -> Result<()> {
+    let mut sink: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    };
+
+    match args.format {
+        ReportFormat::Text => render_text(report, &mut sink),
+        ReportFormat::Json => render_json(report, results_path, &mut sink),
+        ReportFormat::Ndjson => render_ndjson(report, results_path, &mut sink),
     }
-    buf
 }
 
 async fn read_varint(stream: &mut TcpStream) -> Result<i32> {
@@ -117,172 +448,758 @@ async fn read_varint(stream: &mut TcpStream) -> Result<i32> {
     Err(anyhow!("VarInt is way too long"))
 }
 
-fn encode_string(text: &str) -> Vec<u8> {
-    let bytes = text.as_bytes();
-    let mut buf = encode_varint(bytes.len() as i32);
-    buf.extend_from_slice(bytes);
-    buf
+// Once the Encryption Response goes out, everything in both directions is
+// AES-128/CFB8 with the shared secret doubling as the IV. CFB8 needs one
+// register that's updated byte-by-byte across however many read calls the
+// connection ends up making, so this rolls its own keystream instead of
+// going through a one-shot stream-cipher crate. We only ever need the
+// decrypt direction - the client has nothing left to say after the
+// Encryption Response, which itself goes out unencrypted.
+struct CryptoState {
+    cipher: Aes128,
+    dec_register: [u8; 16],
+}
+
+impl CryptoState {
+    fn new(shared_secret: &[u8; 16]) -> Self {
+        CryptoState {
+            cipher: Aes128::new(GenericArray::from_slice(shared_secret)),
+            dec_register: *shared_secret,
+        }
+    }
+
+    fn decrypt(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            let mut block = GenericArray::clone_from_slice(&self.dec_register);
+            self.cipher.encrypt_block(&mut block);
+            let ciphertext_byte = *byte;
+            *byte = ciphertext_byte ^ block[0];
+            self.dec_register.rotate_left(1);
+            self.dec_register[15] = ciphertext_byte;
+        }
+    }
+}
+
+// Same read helpers as the plain status path, but transparently decrypting
+// once a post-handshake CryptoState exists
+async fn read_varint_enc(stream: &mut TcpStream, crypto: &mut Option<CryptoState>) -> Result<i32> {
+    let mut result = 0i32;
+    let mut shift = 0;
+
+    for _ in 0..5 {
+        let mut buf = [0u8; 1];
+        stream.read_exact(&mut buf).await?;
+        if let Some(c) = crypto {
+            c.decrypt(&mut buf);
+        }
+        let b = buf[0];
+        result |= ((b & 0x7F) as i32) << shift;
+        if b & 0x80 == 0 {
+            return Ok(result);
+        }
+        shift += 7;
+    }
+
+    Err(anyhow!("VarInt is way too long"))
+}
+
+async fn read_exact_enc(stream: &mut TcpStream, buf: &mut [u8], crypto: &mut Option<CryptoState>) -> Result<()> {
+    stream.read_exact(buf).await?;
+    if let Some(c) = crypto {
+        c.decrypt(buf);
+    }
+    Ok(())
+}
+
+// Mojang's "is this hash negative" hex encoding: treat the SHA-1 digest as a
+// signed 160-bit big-endian integer and render it as such
+fn mc_server_hash(server_id: &str, shared_secret: &[u8], public_key_der: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(server_id.as_bytes());
+    hasher.update(shared_secret);
+    hasher.update(public_key_der);
+    let mut digest: [u8; 20] = hasher.finalize().into();
+
+    let negative = digest[0] & 0x80 != 0;
+    if negative {
+        let mut carry = 1u16;
+        for byte in digest.iter_mut().rev() {
+            let v = (!*byte as u16) + carry;
+            *byte = v as u8;
+            carry = v >> 8;
+        }
+    }
+
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    let trimmed = hex.trim_start_matches('0');
+    let trimmed = if trimmed.is_empty() { "0" } else { trimmed };
+    if negative {
+        format!("-{}", trimmed)
+    } else {
+        trimmed.to_string()
+    }
+}
+
+// Confirms a premium account actually owns the session by hitting Mojang's
+// join endpoint - only attempted when MCPROBE_MOJANG_ACCESS_TOKEN is set
+async fn mojang_join(access_token: &str, profile_uuid: &str, server_hash: &str) -> Result<bool> {
+    let client = reqwest::Client::new();
+    let resp = client
+        .post("https://sessionserver.mojang.com/session/minecraft/join")
+        .json(&serde_json::json!({
+            "accessToken": access_token,
+            "selectedProfile": profile_uuid,
+            "serverId": server_hash,
+        }))
+        .send()
+        .await?;
+    Ok(resp.status().as_u16() == 204)
+}
+
+// Handshake, status request, login-start (one struct per protocol variant,
+// since the wire format itself differs) and the encryption/compression
+// packets all just implement Serializable and go through codec::frame_packet
+// for the id + length wrapping, instead of each hand-building a Vec<u8>.
+struct Handshake {
+    protocol_version: codec::VarInt,
+    server_address: String,
+    server_port: u16,
+    next_state: codec::VarInt,
+}
+
+impl Serializable for Handshake {
+    fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        Ok(Handshake {
+            protocol_version: codec::VarInt::read_from(r)?,
+            server_address: String::read_from(r)?,
+            server_port: u16::read_from(r)?,
+            next_state: codec::VarInt::read_from(r)?,
+        })
+    }
+
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.protocol_version.write_to(w)?;
+        self.server_address.write_to(w)?;
+        self.server_port.write_to(w)?;
+        self.next_state.write_to(w)?;
+        Ok(())
+    }
+}
+
+// 1.8 to 1.18.2 - just a username
+struct LoginStartLegacy {
+    username: String,
+}
+
+impl Serializable for LoginStartLegacy {
+    fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        Ok(LoginStartLegacy { username: String::read_from(r)? })
+    }
+
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.username.write_to(w)
+    }
+}
+
+// 1.19 added a "has signature data" bool, no uuid yet
+struct LoginStart119 {
+    username: String,
+}
+
+impl Serializable for LoginStart119 {
+    fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        let username = String::read_from(r)?;
+        let _has_signature = bool::read_from(r)?;
+        Ok(LoginStart119 { username })
+    }
+
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.username.write_to(w)?;
+        false.write_to(w) // no signature data
+    }
+}
+
+// 1.19.2 - signature bool plus an optional uuid
+struct LoginStart1192 {
+    username: String,
+    uuid: Option<codec::Uuid>,
+}
+
+impl Serializable for LoginStart1192 {
+    fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        let username = String::read_from(r)?;
+        let _has_signature = bool::read_from(r)?;
+        let has_uuid = bool::read_from(r)?;
+        let uuid = if has_uuid { Some(codec::Uuid::read_from(r)?) } else { None };
+        Ok(LoginStart1192 { username, uuid })
+    }
+
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.username.write_to(w)?;
+        false.write_to(w)?; // no signature data
+        self.uuid.is_some().write_to(w)?;
+        if let Some(uuid) = &self.uuid {
+            uuid.write_to(w)?;
+        }
+        Ok(())
+    }
+}
+
+// 1.19.3 to 1.20.1 - signature bool is gone again, uuid is still optional
+struct LoginStart1193 {
+    username: String,
+    uuid: Option<codec::Uuid>,
+}
+
+impl Serializable for LoginStart1193 {
+    fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        let username = String::read_from(r)?;
+        let has_uuid = bool::read_from(r)?;
+        let uuid = if has_uuid { Some(codec::Uuid::read_from(r)?) } else { None };
+        Ok(LoginStart1193 { username, uuid })
+    }
+
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.username.write_to(w)?;
+        self.uuid.is_some().write_to(w)?;
+        if let Some(uuid) = &self.uuid {
+            uuid.write_to(w)?;
+        }
+        Ok(())
+    }
+}
+
+// 1.20.2+ - uuid is mandatory, not optional
+struct LoginStart1202 {
+    username: String,
+    uuid: codec::Uuid,
+}
+
+impl Serializable for LoginStart1202 {
+    fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        Ok(LoginStart1202 {
+            username: String::read_from(r)?,
+            uuid: codec::Uuid::read_from(r)?,
+        })
+    }
+
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.username.write_to(w)?;
+        self.uuid.write_to(w)
+    }
+}
+
+struct EncryptionRequest {
+    server_id: String,
+    public_key: Vec<u8>,
+    verify_token: Vec<u8>,
+}
+
+impl Serializable for EncryptionRequest {
+    fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        Ok(EncryptionRequest {
+            server_id: String::read_from(r)?,
+            public_key: Vec::<u8>::read_from(r)?,
+            verify_token: Vec::<u8>::read_from(r)?,
+        })
+    }
+
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.server_id.write_to(w)?;
+        self.public_key.write_to(w)?;
+        self.verify_token.write_to(w)
+    }
+}
+
+struct EncryptionResponse {
+    shared_secret: Vec<u8>,
+    verify_token: Vec<u8>,
+}
+
+impl Serializable for EncryptionResponse {
+    fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        Ok(EncryptionResponse {
+            shared_secret: Vec::<u8>::read_from(r)?,
+            verify_token: Vec::<u8>::read_from(r)?,
+        })
+    }
+
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.shared_secret.write_to(w)?;
+        self.verify_token.write_to(w)
+    }
+}
+
+struct SetCompression {
+    threshold: codec::VarInt,
+}
+
+impl Serializable for SetCompression {
+    fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        Ok(SetCompression { threshold: codec::VarInt::read_from(r)? })
+    }
+
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.threshold.write_to(w)
+    }
+}
+
+struct Disconnect {
+    reason: String,
+}
+
+impl Serializable for Disconnect {
+    fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        Ok(Disconnect { reason: String::read_from(r)? })
+    }
+
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.reason.write_to(w)
+    }
 }
 
 // Creates the initial handshake packet
-fn create_handshake_packet(host: &str, port: u16, next_state: i32, protocol: i32) -> Vec<u8> {
-    let mut data = Vec::new();
-    
-    data.extend_from_slice(&encode_varint(0x00)); // packet id
-    data.extend_from_slice(&encode_varint(protocol));
-    data.extend_from_slice(&encode_string(host));
-    data.extend_from_slice(&port.to_be_bytes());
-    data.extend_from_slice(&encode_varint(next_state));
-    
-    // prepend the length
-    let mut packet = encode_varint(data.len() as i32);
-    packet.extend_from_slice(&data);
-    packet
+fn create_handshake_packet(host: &str, port: u16, next_state: i32, protocol: i32) -> Result<Vec<u8>> {
+    let handshake = Handshake {
+        protocol_version: codec::VarInt(protocol),
+        server_address: host.to_string(),
+        server_port: port,
+        next_state: codec::VarInt(next_state),
+    };
+    codec::frame_packet(0x00, &handshake)
 }
 
-fn create_status_request() -> Vec<u8> {
-    vec![0x01, 0x00]
+fn create_status_request() -> Result<Vec<u8>> {
+    codec::frame_packet(0x00, &())
 }
 
 // Login packet - has to handle different protocol versions because Mojang
-fn create_login_start(username: &str, uuid: &str, protocol: i32) -> Vec<u8> {
-    let mut data = Vec::new();
-    
-    data.extend_from_slice(&encode_varint(0x00)); // login start packet id
-    data.extend_from_slice(&encode_string(username));
-    
-    // Different versions want different data formats
+fn create_login_start(username: &str, uuid: &str, protocol: i32) -> Result<Vec<u8>> {
+    let username = username.to_string();
+
     if protocol >= 47 && protocol <= 758 {
-        // 1.8 to 1.18.2 - just username
+        codec::frame_packet(0x00, &LoginStartLegacy { username })
     } else if protocol == 759 {
-        // 1.19 added signature stuff
-        data.push(0x00); // no signature data
+        codec::frame_packet(0x00, &LoginStart119 { username })
     } else if protocol == 760 {
-        // 1.19.2 - signature + optional uuid
-        data.push(0x00); // no sig
-        data.push(0x01); // has uuid
-        let uuid_bytes = parse_uuid(uuid);
-        data.extend_from_slice(&uuid_bytes);
+        let uuid = Some(parse_uuid(uuid));
+        codec::frame_packet(0x00, &LoginStart1192 { username, uuid })
     } else if protocol >= 761 && protocol <= 763 {
-        // 1.19.3 to 1.20.1
-        data.push(0x01); // has uuid
-        let uuid_bytes = parse_uuid(uuid);
-        data.extend_from_slice(&uuid_bytes);
-    } else if protocol >= 764 {
+        let uuid = Some(parse_uuid(uuid));
+        codec::frame_packet(0x00, &LoginStart1193 { username, uuid })
+    } else {
         // 1.20.2+ always requires uuid
-        let uuid_bytes = parse_uuid(uuid);
-        data.extend_from_slice(&uuid_bytes);
+        codec::frame_packet(0x00, &LoginStart1202 { username, uuid: parse_uuid(uuid) })
     }
-    
-    let mut packet = encode_varint(data.len() as i32);
-    packet.extend_from_slice(&data);
-    packet
 }
 
-fn parse_uuid(uuid: &str) -> Vec<u8> {
-    let clean = uuid.replace("-", "");
-    (0..clean.len())
+fn parse_uuid(uuid: &str) -> codec::Uuid {
+    let clean = uuid.replace('-', "");
+    let bytes: Vec<u8> = (0..clean.len())
         .step_by(2)
         .map(|i| u8::from_str_radix(&clean[i..i + 2], 16).unwrap_or(0))
-        .collect()
+        .collect();
+    let mut msb = [0u8; 8];
+    let mut lsb = [0u8; 8];
+    msb.copy_from_slice(&bytes[0..8]);
+    lsb.copy_from_slice(&bytes[8..16]);
+    codec::Uuid {
+        most_significant: u64::from_be_bytes(msb),
+        least_significant: u64::from_be_bytes(lsb),
+    }
+}
+
+// Resolved formatting for a chat component - `None` on a field means "not
+// set here, fall through to whatever the parent had", which is what makes
+// `resolved` below an inheritance merge rather than a plain overwrite.
+#[derive(Debug, Clone, Default)]
+struct ChatStyle {
+    color: Option<String>,
+    bold: Option<bool>,
+    italic: Option<bool>,
+    underlined: Option<bool>,
+    strikethrough: Option<bool>,
+    obfuscated: Option<bool>,
+}
+
+impl ChatStyle {
+    fn from_json(obj: &serde_json::Map<String, serde_json::Value>) -> ChatStyle {
+        ChatStyle {
+            color: obj.get("color").and_then(|v| v.as_str()).map(str::to_string),
+            bold: obj.get("bold").and_then(|v| v.as_bool()),
+            italic: obj.get("italic").and_then(|v| v.as_bool()),
+            underlined: obj.get("underlined").and_then(|v| v.as_bool()),
+            strikethrough: obj.get("strikethrough").and_then(|v| v.as_bool()),
+            obfuscated: obj.get("obfuscated").and_then(|v| v.as_bool()),
+        }
+    }
+
+    fn resolved(&self, parent: &ChatStyle) -> ChatStyle {
+        ChatStyle {
+            color: self.color.clone().or_else(|| parent.color.clone()),
+            bold: self.bold.or(parent.bold),
+            italic: self.italic.or(parent.italic),
+            underlined: self.underlined.or(parent.underlined),
+            strikethrough: self.strikethrough.or(parent.strikethrough),
+            obfuscated: self.obfuscated.or(parent.obfuscated),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MotdRenderMode {
+    PlainText,
+    Ansi,
+}
+
+// A deliberately small built-in set - just enough to render the translate
+// keys that actually turn up in MOTDs and kick messages, not a full copy of
+// Mojang's lang file. Unknown keys fall back to the raw key, same as vanilla
+// does when it can't find a translation either.
+const TRANSLATIONS: &[(&str, &str)] = &[
+    ("chat.type.text", "<%s> %s"),
+    ("chat.type.announcement", "[%s] %s"),
+    ("multiplayer.disconnect.server_full", "The server is full"),
+    ("multiplayer.disconnect.kicked", "Kicked by an operator"),
+    ("multiplayer.disconnect.not_whitelisted", "You are not white-listed on this server"),
+    ("multiplayer.disconnect.outdated_client", "Outdated client! Please use %s"),
+    ("multiplayer.disconnect.outdated_server", "Outdated server! I'm still on %s"),
+];
+
+fn resolve_translate(key: &str, args: &[String]) -> String {
+    let pattern = TRANSLATIONS
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, p)| *p)
+        .unwrap_or(key);
+    substitute_placeholders(pattern, args)
 }
 
-// Parse MOTD - servers can send this in multiple formats
-fn parse_motd(desc: &serde_json::Value) -> String {
-    match desc {
-        serde_json::Value::String(s) => strip_color_codes(s),
+// Handles both the plain `%s` (consumes the next arg in order) and the
+// positional `%1$s` forms Java's format strings use
+fn substitute_placeholders(pattern: &str, args: &[String]) -> String {
+    let mut out = String::new();
+    let mut chars = pattern.chars().peekable();
+    let mut next_implicit = 0usize;
+
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            out.push(ch);
+            continue;
+        }
+
+        let mut digits = String::new();
+        while let Some(&d) = chars.peek() {
+            if d.is_ascii_digit() {
+                digits.push(d);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+
+        if digits.is_empty() {
+            match chars.next() {
+                Some('s') => {
+                    out.push_str(args.get(next_implicit).map(String::as_str).unwrap_or(""));
+                    next_implicit += 1;
+                }
+                Some('%') => out.push('%'),
+                Some(other) => {
+                    out.push('%');
+                    out.push(other);
+                }
+                None => out.push('%'),
+            }
+        } else if chars.peek() == Some(&'$') {
+            chars.next(); // consume '$'
+            if chars.peek() == Some(&'s') {
+                chars.next();
+                let idx: usize = digits.parse().unwrap_or(1);
+                out.push_str(args.get(idx.saturating_sub(1)).map(String::as_str).unwrap_or(""));
+            }
+        } else {
+            out.push('%');
+            out.push_str(&digits);
+        }
+    }
+
+    out
+}
+
+// Parse MOTD - servers can send this as a bare string, a single component
+// object, or an array where the first element is the parent and the rest
+// are siblings that inherit its style. Recurses through `extra` and
+// `translate`/`with` so nested components and translated text aren't lost.
+fn parse_motd(desc: &serde_json::Value, mode: MotdRenderMode) -> String {
+    let mut out = String::new();
+    render_component(desc, &ChatStyle::default(), mode, &mut out);
+    out
+}
+
+fn render_component(value: &serde_json::Value, inherited: &ChatStyle, mode: MotdRenderMode, out: &mut String) {
+    match value {
+        serde_json::Value::String(s) => render_leaf_text(s, inherited, mode, out),
         serde_json::Value::Object(obj) => {
-            let mut motd = String::new();
-            
-            if let Some(serde_json::Value::String(text)) = obj.get("text") {
-                motd.push_str(&strip_color_codes(text));
+            let style = ChatStyle::from_json(obj).resolved(inherited);
+
+            if let Some(text) = obj.get("text").and_then(|v| v.as_str()) {
+                render_leaf_text(text, &style, mode, out);
             }
-            
-            if let Some(extra) = obj.get("extra") {
-                motd.push_str(&parse_extra(extra));
+
+            if let Some(key) = obj.get("translate").and_then(|v| v.as_str()) {
+                let args: Vec<String> = obj
+                    .get("with")
+                    .and_then(|v| v.as_array())
+                    .map(|items| {
+                        items
+                            .iter()
+                            .map(|item| {
+                                let mut buf = String::new();
+                                render_component(item, &style, mode, &mut buf);
+                                buf
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                render_leaf_text(&resolve_translate(key, &args), &style, mode, out);
+            }
+
+            if let Some(extra) = obj.get("extra").and_then(|v| v.as_array()) {
+                for child in extra {
+                    render_component(child, &style, mode, out);
+                }
             }
-            
-            motd
         }
         serde_json::Value::Array(arr) => {
-            arr.iter()
-                .filter_map(|v| {
-                    if let serde_json::Value::Object(obj) = v {
-                        obj.get("text")
-                            .and_then(|t| t.as_str())
-                            .map(|s| strip_color_codes(s))
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<_>>()
-                .join("")
-        }
-        _ => String::new(),
-    }
-}
-
-fn parse_extra(extra: &serde_json::Value) -> String {
-    match extra {
-        serde_json::Value::Array(arr) => arr
-            .iter()
-            .map(|item| {
-                if let serde_json::Value::Object(obj) = item {
-                    obj.get("text")
-                        .and_then(|t| t.as_str())
-                        .map(|s| strip_color_codes(s))
-                        .unwrap_or_default()
-                } else if let serde_json::Value::String(s) = item {
-                    strip_color_codes(s)
+            if let Some((first, siblings)) = arr.split_first() {
+                render_component(first, inherited, mode, out);
+                let parent_style = component_style(first, inherited);
+                for sibling in siblings {
+                    render_component(sibling, &parent_style, mode, out);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// The resolved style a component would render with, without rendering its
+// text - needed so array siblings can inherit the parent's style
+fn component_style(value: &serde_json::Value, inherited: &ChatStyle) -> ChatStyle {
+    match value {
+        serde_json::Value::Object(obj) => ChatStyle::from_json(obj).resolved(inherited),
+        _ => inherited.clone(),
+    }
+}
+
+fn render_leaf_text(text: &str, style: &ChatStyle, mode: MotdRenderMode, out: &mut String) {
+    match mode {
+        MotdRenderMode::PlainText => out.push_str(&strip_color_codes(text)),
+        MotdRenderMode::Ansi => {
+            for (seg_style, seg_text) in split_legacy_segments(text, style) {
+                let prefix = ansi_prefix(&seg_style);
+                if prefix.is_empty() {
+                    out.push_str(&seg_text);
                 } else {
-                    String::new()
+                    // seg_text can itself embed a reset (e.g. a translate arg
+                    // that was pre-rendered with its own style) - since SGR
+                    // state is flat, that reset would wipe our own styling for
+                    // everything after it, so reassert our prefix right away
+                    out.push_str(&prefix);
+                    out.push_str(&seg_text.replace(ANSI_RESET, &format!("{}{}", ANSI_RESET, prefix)));
+                    out.push_str(ANSI_RESET);
                 }
-            })
-            .collect::<Vec<_>>()
-            .join(""),
-        _ => String::new(),
+            }
+        }
     }
 }
 
-// Remove minecraft color codes (Â§c, Â§l, etc)
+// Remove minecraft color codes (§c, §l, etc)
 fn strip_color_codes(text: &str) -> String {
     let mut result = String::new();
     let mut chars = text.chars();
-    
+
     while let Some(ch) = chars.next() {
-        if ch == 'Â§' {
+        if ch == '§' {
             chars.next(); // skip the color code
         } else {
             result.push(ch);
         }
     }
-    
+
     result
 }
 
-// Get basic server info
-async fn get_server_status(host: &str, port: u16) -> Result<ServerResponse> {
+// Legacy `§`-codes inside a text leaf override the component's own style
+// from that point on, same as they do in a real client - `§r` resets all
+// the way back to no color/no formatting, not just back to the inherited
+// component style.
+fn split_legacy_segments(text: &str, base: &ChatStyle) -> Vec<(ChatStyle, String)> {
+    let mut segments = Vec::new();
+    let mut style = base.clone();
+    let mut current = String::new();
+    let mut chars = text.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch == '§' {
+            if let Some(code) = chars.next() {
+                if !current.is_empty() {
+                    segments.push((style.clone(), std::mem::take(&mut current)));
+                }
+                apply_legacy_code(&mut style, code.to_ascii_lowercase());
+            }
+        } else {
+            current.push(ch);
+        }
+    }
+
+    if !current.is_empty() {
+        segments.push((style, current));
+    }
+
+    segments
+}
+
+fn apply_legacy_code(style: &mut ChatStyle, code: char) {
+    if let Some(name) = legacy_color_name(code) {
+        *style = ChatStyle {
+            color: Some(name.to_string()),
+            ..ChatStyle::default()
+        };
+        return;
+    }
+
+    match code {
+        'k' => style.obfuscated = Some(true),
+        'l' => style.bold = Some(true),
+        'm' => style.strikethrough = Some(true),
+        'n' => style.underlined = Some(true),
+        'o' => style.italic = Some(true),
+        'r' => *style = ChatStyle::default(),
+        _ => {}
+    }
+}
+
+fn legacy_color_name(code: char) -> Option<&'static str> {
+    match code {
+        '0' => Some("black"),
+        '1' => Some("dark_blue"),
+        '2' => Some("dark_green"),
+        '3' => Some("dark_aqua"),
+        '4' => Some("dark_red"),
+        '5' => Some("dark_purple"),
+        '6' => Some("gold"),
+        '7' => Some("gray"),
+        '8' => Some("dark_gray"),
+        '9' => Some("blue"),
+        'a' => Some("green"),
+        'b' => Some("aqua"),
+        'c' => Some("red"),
+        'd' => Some("light_purple"),
+        'e' => Some("yellow"),
+        'f' => Some("white"),
+        _ => None,
+    }
+}
+
+const ANSI_RESET: &str = "\x1b[0m";
+
+fn ansi_prefix(style: &ChatStyle) -> String {
+    let mut codes = Vec::new();
+
+    if let Some(color) = &style.color {
+        if let Some(code) = ansi_color_code(color) {
+            codes.push(code);
+        }
+    }
+    if style.bold == Some(true) {
+        codes.push("1".to_string());
+    }
+    if style.italic == Some(true) {
+        codes.push("3".to_string());
+    }
+    if style.underlined == Some(true) {
+        codes.push("4".to_string());
+    }
+    if style.strikethrough == Some(true) {
+        codes.push("9".to_string());
+    }
+    // obfuscated has no real ANSI equivalent - closest is blink, which most
+    // terminals ignore anyway, so skip it rather than produce garbled output
+
+    if codes.is_empty() {
+        String::new()
+    } else {
+        format!("\x1b[{}m", codes.join(";"))
+    }
+}
+
+// Named MC colors map to the standard 16-color palette; `#rrggbb` (1.16+)
+// becomes a 24-bit true-color escape instead
+fn ansi_color_code(color: &str) -> Option<String> {
+    if let Some(hex) = color.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
+        let g = u8::from_str_radix(&hex[2..4], 16).ok()?;
+        let b = u8::from_str_radix(&hex[4..6], 16).ok()?;
+        return Some(format!("38;2;{};{};{}", r, g, b));
+    }
+
+    let code = match color {
+        "black" => "30",
+        "dark_red" => "31",
+        "dark_green" => "32",
+        "gold" => "33",
+        "dark_blue" => "34",
+        "dark_purple" => "35",
+        "dark_aqua" => "36",
+        "gray" => "37",
+        "dark_gray" => "90",
+        "red" => "91",
+        "green" => "92",
+        "yellow" => "93",
+        "blue" => "94",
+        "light_purple" => "95",
+        "aqua" => "96",
+        "white" => "97",
+        _ => return None,
+    };
+    Some(code.to_string())
+}
+
+// A plain TCP connect, split out from get_server_status so callers can tell
+// "never got a connection" apart from "connected fine, but the modern
+// status handshake didn't work" - the legacy ping fallback only makes sense
+// for the latter, since a closed/filtered/non-Minecraft host won't answer
+// the legacy ping either and isn't worth a second connection attempt.
+async fn connect_with_timeout(host: &str, port: u16) -> Result<TcpStream> {
     let addr: SocketAddr = format!("{}:{}", host, port).parse()?;
-    let mut stream = timeout(DEFAULT_TIMEOUT, TcpStream::connect(addr)).await??;
-    
+    Ok(timeout(DEFAULT_TIMEOUT, TcpStream::connect(addr)).await??)
+}
+
+// Get basic server info over an already-connected stream
+async fn get_server_status(stream: &mut TcpStream, host: &str, port: u16) -> Result<ServerResponse> {
     // Send handshake with high protocol so server tells us its real version
-    let handshake = create_handshake_packet(host, port, 1, MAX_PROTOCOL_VERSION);
+    let handshake = create_handshake_packet(host, port, 1, MAX_PROTOCOL_VERSION)?;
     stream.write_all(&handshake).await?;
     stream.flush().await?;
-    
+
     // Ask for status
-    let status_req = create_status_request();
+    let status_req = create_status_request()?;
     stream.write_all(&status_req).await?;
     stream.flush().await?;
-    
+
     // Read the response
-    let _pkt_len = read_varint(&mut stream).await?;
-    let _pkt_id = read_varint(&mut stream).await?;
-    let json_len = read_varint(&mut stream).await?;
-    
+    let _pkt_len = read_varint(stream).await?;
+    let _pkt_id = read_varint(stream).await?;
+    let json_len = read_varint(stream).await?;
+
     let mut json_data = vec![0u8; json_len as usize];
     stream.read_exact(&mut json_data).await?;
-    
+
     let response: ServerResponse = serde_json::from_slice(&json_data)?;
     Ok(response)
 }
@@ -295,44 +1212,39 @@ async fn get_auth_mode(host: &str, port: u16, protocol: i32) -> Result<i32> {
     let addr: SocketAddr = format!("{}:{}", host, port).parse()?;
     let mut stream = timeout(DEFAULT_TIMEOUT, TcpStream::connect(addr)).await??;
     
-    let handshake = create_handshake_packet(host, port, 2, protocol);
+    let handshake = create_handshake_packet(host, port, 2, protocol)?;
     stream.write_all(&handshake).await?;
     stream.flush().await?;
-    
-    let login = create_login_start("popiiumaa", "00000000-0000-0000-0000-000000000000", protocol);
+
+    let login = create_login_start("popiiumaa", "00000000-0000-0000-0000-000000000000", protocol)?;
     stream.write_all(&login).await?;
     stream.flush().await?;
-    
+
     let mut compression = -1;
-    
+    let mut crypto: Option<CryptoState> = None;
+
+    // Only set if MCPROBE_MOJANG_ACCESS_TOKEN is present - lets the scan
+    // actually complete a premium join instead of just spotting the request
+    let mojang_access_token = std::env::var("MCPROBE_MOJANG_ACCESS_TOKEN").ok();
+    let mojang_profile_uuid = std::env::var("MCPROBE_MOJANG_UUID")
+        .unwrap_or_else(|_| "00000000-0000-0000-0000-000000000000".to_string());
+
     let result = timeout(AUTH_TIMEOUT, async {
         loop {
-            let pkt_len = read_varint(&mut stream).await?;
+            let pkt_len = read_varint_enc(&mut stream, &mut crypto).await?;
             if pkt_len <= 0 { continue; }
-            
+
             let mut pkt_data = vec![0u8; pkt_len as usize];
-            stream.read_exact(&mut pkt_data).await?;
-            
+            read_exact_enc(&mut stream, &mut pkt_data, &mut crypto).await?;
+
             let pkt_bytes = if compression >= 0 {
-                let mut pos = 0;
-                let mut dlen = 0i32;
-                let mut bits = 0;
-                
-                for _ in 0..5 {
-                    if pos >= pkt_data.len() {
-                        return Err(anyhow!("bad compressed packet"));
-                    }
-                    let b = pkt_data[pos];
-                    pos += 1;
-                    dlen |= ((b & 0x7F) as i32) << bits;
-                    if b & 0x80 == 0 { break; }
-                    bits += 7;
-                }
-                
+                let mut body_cursor = &pkt_data[..];
+                let dlen = codec::VarInt::read_from(&mut body_cursor)?.0;
+
                 if dlen == 0 {
-                    pkt_data[pos..].to_vec()
+                    body_cursor.to_vec()
                 } else {
-                    let mut decoder = ZlibDecoder::new(&pkt_data[pos..]);
+                    let mut decoder = ZlibDecoder::new(body_cursor);
                     let mut out = Vec::new();
                     decoder.read_to_end(&mut out)?;
                     out
@@ -340,83 +1252,345 @@ async fn get_auth_mode(host: &str, port: u16, protocol: i32) -> Result<i32> {
             } else {
                 pkt_data
             };
-            
+
             if pkt_bytes.is_empty() { continue; }
-            
-            let mut pos = 0;
-            let mut id = 0i32;
-            let mut bits = 0;
-            
-            for _ in 0..5 {
-                if pos >= pkt_bytes.len() { 
-                    return Err(anyhow!("bad packet"));
-                }
-                let b = pkt_bytes[pos];
-                pos += 1;
-                id |= ((b & 0x7F) as i32) << bits;
-                if b & 0x80 == 0 { break; }
-                bits += 7;
-            }
-            
+
+            let mut cursor = &pkt_bytes[..];
+            let id = codec::VarInt::read_from(&mut cursor)?.0;
+
             match id {
                 0x00 => {
-                    // kick/disconnect
-                    if pos < pkt_bytes.len() {
-                        let mut slen = 0i32;
-                        let mut bits = 0;
-                        for _ in 0..5 {
-                            if pos >= pkt_bytes.len() { break; }
-                            let b = pkt_bytes[pos];
-                            pos += 1;
-                            slen |= ((b & 0x7F) as i32) << bits;
-                            if b & 0x80 == 0 { break; }
-                            bits += 7;
-                        }
-                        
-                        if slen > 0 && pos + slen as usize <= pkt_bytes.len() {
-                            if let Ok(msg) = std::str::from_utf8(&pkt_bytes[pos..pos + slen as usize]) {
-                                if msg.to_lowercase().contains("whitelist") {
-                                    return Ok(2);
-                                }
-                            }
+                    // kick/disconnect - a reason mentioning the whitelist counts
+                    // as whitelisted either way; otherwise a kick before
+                    // encryption is a plain offline-mode kick, and past
+                    // encryption it means a rejected join
+                    if let Ok(disconnect) = Disconnect::read_from(&mut cursor) {
+                        if disconnect.reason.to_lowercase().contains("whitelist") {
+                            return Ok(2);
                         }
                     }
-                    return Ok(2);
+                    return Ok(if crypto.is_some() { 3 } else { 2 });
                 }
-                0x01 => return Ok(1), // encryption = online
-                0x02 => return Ok(0), // success = cracked
+                0x01 => {
+                    // Encryption Request: server id, RSA public key (DER), verify token
+                    let enc_request = EncryptionRequest::read_from(&mut cursor)?;
+
+                    let public_key = RsaPublicKey::from_public_key_der(&enc_request.public_key)
+                        .map_err(|e| anyhow!("bad server public key: {}", e))?;
+
+                    let mut shared_secret = [0u8; 16];
+                    rand::thread_rng().fill_bytes(&mut shared_secret);
+
+                    let (encrypted_secret, encrypted_token) = {
+                        let mut rng = rand::thread_rng();
+                        let encrypted_secret = public_key
+                            .encrypt(&mut rng, Pkcs1v15Encrypt, &shared_secret)
+                            .map_err(|e| anyhow!("failed to encrypt shared secret: {}", e))?;
+                        let encrypted_token = public_key
+                            .encrypt(&mut rng, Pkcs1v15Encrypt, &enc_request.verify_token)
+                            .map_err(|e| anyhow!("failed to encrypt verify token: {}", e))?;
+                        (encrypted_secret, encrypted_token)
+                    };
+
+                    let response = EncryptionResponse {
+                        shared_secret: encrypted_secret,
+                        verify_token: encrypted_token,
+                    };
+                    let packet = codec::frame_packet(0x01, &response)?;
+                    stream.write_all(&packet).await?;
+                    stream.flush().await?;
+
+                    // Everything from here on (both directions) is encrypted
+                    crypto = Some(CryptoState::new(&shared_secret));
+
+                    if let Some(token) = &mojang_access_token {
+                        let server_hash =
+                            mc_server_hash(&enc_request.server_id, &shared_secret, &enc_request.public_key);
+                        // Best-effort: whether this succeeds or not, the server's
+                        // own Login Success / Disconnect is the ground truth
+                        let _ = mojang_join(token, &mojang_profile_uuid, &server_hash).await;
+                    }
+                }
+                // Login Success: offline-mode servers skip encryption entirely and
+                // send this straight away; online-mode servers only send it once
+                // our join has actually been verified by Mojang
+                0x02 => return Ok(if crypto.is_some() { 1 } else { 0 }),
                 0x03 => {
                     // compression enabled
-                    let mut thresh = 0i32;
-                    let mut bits = 0;
-                    for _ in 0..5 {
-                        if pos >= pkt_bytes.len() { break; }
-                        let b = pkt_bytes[pos];
-                        pos += 1;
-                        thresh |= ((b & 0x7F) as i32) << bits;
-                        if b & 0x80 == 0 { break; }
-                        bits += 7;
-                    }
-                    compression = thresh;
+                    let set_compression = SetCompression::read_from(&mut cursor)?;
+                    compression = set_compression.threshold.0;
                 }
                 _ => {} // ignore other packets
             }
         }
     })
     .await;
-    
+
     match result {
         Ok(m) => m,
         Err(_) => Ok(-1),
     }
 }
 
-async fn scan_server(ip: String, port: u16, check_auth: bool) -> ScanResult {
+// What the pre-1.8 "legacy" Server List Ping gives back - a subset of what
+// the modern JSON status has, but enough to populate the same ScanResult
+// fields so a 1.6-era server doesn't just show up as a bare error.
+struct LegacyStatus {
+    version: Option<String>,
+    motd: String,
+    online_players: Option<i32>,
+    max_players: Option<i32>,
+}
+
+fn write_utf16_str(buf: &mut Vec<u8>, s: &str) {
+    let units: Vec<u16> = s.encode_utf16().collect();
+    buf.extend_from_slice(&(units.len() as u16).to_be_bytes());
+    for unit in units {
+        buf.extend_from_slice(&unit.to_be_bytes());
+    }
+}
+
+fn utf16be_to_string(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+    String::from_utf16_lossy(&units)
+}
+
+// Fallback for servers too old for the modern JSON status: 0xFE 0x01 plus
+// the 1.6 "MC|PingHost" plugin-message payload. 1.6+ servers answer with
+// the §1-prefixed reply; older ones that don't recognise the payload
+// just ignore it and answer with their plain three-field reply instead -
+// either way we get a single 0xFF kick packet back.
+async fn legacy_server_list_ping(host: &str, port: u16) -> Result<LegacyStatus> {
+    let addr: SocketAddr = format!("{}:{}", host, port).parse()?;
+    let mut stream = timeout(DEFAULT_TIMEOUT, TcpStream::connect(addr)).await??;
+
+    let mut payload = Vec::new();
+    payload.push(127u8); // protocol version - any recent-enough value works for a ping
+    write_utf16_str(&mut payload, host);
+    payload.extend_from_slice(&(port as i32).to_be_bytes());
+
+    let mut packet = vec![0xFE, 0x01, 0xFA];
+    write_utf16_str(&mut packet, "MC|PingHost");
+    packet.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    packet.extend_from_slice(&payload);
+
+    stream.write_all(&packet).await?;
+    stream.flush().await?;
+
+    let id = stream.read_u8().await?;
+    if id != 0xFF {
+        return Err(anyhow!("expected a 0xFF kick response, got 0x{:02X}", id));
+    }
+
+    let len = stream.read_u16().await?;
+    let mut buf = vec![0u8; len as usize * 2];
+    stream.read_exact(&mut buf).await?;
+
+    parse_legacy_status(&utf16be_to_string(&buf))
+}
+
+fn parse_legacy_status(text: &str) -> Result<LegacyStatus> {
+    if let Some(rest) = text.strip_prefix('\u{00A7}').and_then(|s| s.strip_prefix('1')) {
+        let fields: Vec<&str> = rest.split('\0').collect();
+        if fields.len() < 6 {
+            return Err(anyhow!("malformed legacy ping response"));
+        }
+        return Ok(LegacyStatus {
+            version: Some(fields[2].to_string()),
+            motd: fields[3].to_string(),
+            online_players: fields[4].parse().ok(),
+            max_players: fields[5].parse().ok(),
+        });
+    }
+
+    let fields: Vec<&str> = text.split('\u{00A7}').collect();
+    if fields.len() < 3 {
+        return Err(anyhow!("malformed legacy ping response"));
+    }
+    Ok(LegacyStatus {
+        version: None,
+        motd: fields[0].to_string(),
+        online_players: fields[1].parse().ok(),
+        max_players: fields[2].parse().ok(),
+    })
+}
+
+// Result of the GameSpy4-derived UDP query protocol - only available when
+// the server has `enable-query` on. Gives a complete player list and a
+// plugin list the TCP status ping has no equivalent field for.
+#[derive(Debug, Default)]
+struct QueryInfo {
+    max_players: Option<i32>,
+    num_players: Option<i32>,
+    plugins: Option<String>,
+    players: Vec<String>,
+}
+
+const QUERY_MAGIC: [u8; 2] = [0xFE, 0xFD];
+
+// Reads a null-terminated ASCII string out of an already-buffered query
+// response, same shape as codec's String::read_from but terminated by a
+// null byte instead of a VarInt length, since that's what this protocol uses
+fn read_cstr(data: &[u8], pos: &mut usize) -> Result<String> {
+    let start = *pos;
+    while *pos < data.len() && data[*pos] != 0 {
+        *pos += 1;
+    }
+    if *pos >= data.len() {
+        return Err(anyhow!("query response ended without a null terminator"));
+    }
+    let s = String::from_utf8_lossy(&data[start..*pos]).to_string();
+    *pos += 1; // skip the null
+    Ok(s)
+}
+
+// Handshake + full-stat exchange: https://wiki.vg/Query
+async fn query_server(host: &str, port: u16) -> Result<QueryInfo> {
+    let addr: SocketAddr = format!("{}:{}", host, port).parse()?;
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    timeout(DEFAULT_TIMEOUT, socket.connect(addr)).await??;
+
+    // Every byte of the session id must have its high nibble zeroed out,
+    // per the spec, so the server can tell it apart from packet padding
+    let session_id: i32 = 1 & 0x0F0F0F0F;
+
+    let mut handshake = Vec::new();
+    handshake.extend_from_slice(&QUERY_MAGIC);
+    handshake.push(0x09); // handshake
+    handshake.extend_from_slice(&session_id.to_be_bytes());
+    timeout(DEFAULT_TIMEOUT, socket.send(&handshake)).await??;
+
+    let mut buf = [0u8; 256];
+    let n = timeout(DEFAULT_TIMEOUT, socket.recv(&mut buf)).await??;
+    let mut pos = 5; // type byte + echoed session id
+    if n < pos {
+        return Err(anyhow!("query handshake response too short"));
+    }
+    let challenge: i32 = read_cstr(&buf[..n], &mut pos)?
+        .parse()
+        .map_err(|_| anyhow!("bad challenge token in query handshake"))?;
+
+    let mut stat_request = Vec::new();
+    stat_request.extend_from_slice(&QUERY_MAGIC);
+    stat_request.push(0x00); // stat
+    stat_request.extend_from_slice(&session_id.to_be_bytes());
+    stat_request.extend_from_slice(&challenge.to_be_bytes());
+    stat_request.extend_from_slice(&[0u8; 4]); // padding requests the full (not basic) stat
+    timeout(DEFAULT_TIMEOUT, socket.send(&stat_request)).await??;
+
+    let mut buf = vec![0u8; 8192];
+    let n = timeout(DEFAULT_TIMEOUT, socket.recv(&mut buf)).await??;
+    parse_full_stat(&buf[..n])
+}
+
+fn parse_full_stat(data: &[u8]) -> Result<QueryInfo> {
+    if data.len() < 5 || data[0] != 0x00 {
+        return Err(anyhow!("unexpected query response type"));
+    }
+    let mut pos = 5; // type byte + 4-byte session id
+
+    // 11 bytes of constant padding ("splitnum\0\x80\x00") ahead of the K/V section
+    if pos + 11 > data.len() {
+        return Err(anyhow!("query response too short for the K/V section"));
+    }
+    pos += 11;
+
+    let mut info = QueryInfo::default();
+    loop {
+        let key = read_cstr(data, &mut pos)?;
+        if key.is_empty() {
+            break;
+        }
+        let value = read_cstr(data, &mut pos)?;
+        match key.as_str() {
+            "numplayers" => info.num_players = value.parse().ok(),
+            "maxplayers" => info.max_players = value.parse().ok(),
+            "plugins" => info.plugins = Some(value).filter(|v| !v.is_empty()),
+            _ => {}
+        }
+    }
+
+    // 10 bytes of constant padding ("\x01player_\x00\x00") ahead of the player list
+    if pos + 10 <= data.len() {
+        pos += 10;
+        loop {
+            match read_cstr(data, &mut pos) {
+                Ok(name) if name.is_empty() => break,
+                Ok(name) => info.players.push(name),
+                Err(_) => break,
+            }
+        }
+    }
+
+    Ok(info)
+}
+
+// Used by --resume to figure out which targets already have a completed
+// line in RESULTS_PATH, so fix_targets' output can be filtered down to just
+// what's left before the scan loop starts. Also seeds the tally with those
+// pre-existing results - the writer reopens RESULTS_PATH in append mode on
+// resume, so the final file (and the results embedded in --format json)
+// cover both runs, and the summary counts need to match that, not just
+// what streamed through this run's channel.
+fn parse_existing_results(ndjson: &str) -> (HashSet<String>, ScanTally) {
+    let mut scanned = HashSet::new();
+    let mut tally = ScanTally::default();
+    for line in ndjson.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Ok(result) = serde_json::from_str::<ScanResult>(line) {
+            scanned.insert(format!("{}:{}", result.ip, result.port));
+            tally.record(&result);
+        }
+    }
+    (scanned, tally)
+}
+
+// Every completed ScanResult comes through this channel to a single writer
+// task instead of piling up in an in-memory Vec for the whole run. That
+// means a crash or Ctrl-C on a multi-million-target scan still leaves
+// everything scanned so far on disk, ready for --resume to pick up from.
+// `tally` starts seeded with whatever --resume already found on disk, so it
+// stays a running total over the whole file rather than just this session.
+async fn run_result_writer(
+    mut rx: mpsc::Receiver<ScanResult>,
+    path: String,
+    append: bool,
+    mut tally: ScanTally,
+) -> Result<ScanTally> {
+    let file = if append {
+        tokio::fs::OpenOptions::new().create(true).append(true).open(&path).await?
+    } else {
+        tokio::fs::File::create(&path).await?
+    };
+    let mut writer = tokio::io::BufWriter::new(file);
+    let mut pending_flush = 0usize;
+
+    while let Some(result) = rx.recv().await {
+        tally.record(&result);
+        let line = serde_json::to_string(&result)?;
+        writer.write_all(line.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+
+        pending_flush += 1;
+        if pending_flush >= 50 {
+            writer.flush().await?;
+            pending_flush = 0;
+        }
+    }
+
+    writer.flush().await?;
+    Ok(tally)
+}
+
+async fn scan_server(ip: String, port: u16, check_auth: bool, check_query: bool) -> ScanResult {
     let scan_result = timeout(Duration::from_secs(10), async {
         let mut res = ScanResult {
             ip: ip.clone(),
             port,
             motd: None,
+            motd_ansi: None,
             version: None,
             protocol: None,
             max_players: None,
@@ -424,10 +1598,20 @@ async fn scan_server(ip: String, port: u16, check_auth: bool) -> ScanResult {
             players: None,
             favicon: None,
             auth_mode: None,
+            plugins: None,
             error: None,
         };
         
-        match get_server_status(&ip, port).await {
+        // Connect once up front so a dead/filtered host (the common case
+        // across a broad scan) is recognized before trying anything else,
+        // instead of paying for a second connection attempt on every miss.
+        let mut connected_stream = connect_with_timeout(&ip, port).await;
+        let status = match connected_stream {
+            Ok(ref mut stream) => get_server_status(stream, &ip, port).await,
+            Err(ref e) => Err(anyhow!("{}", e)),
+        };
+
+        match status {
             Ok(resp) => {
                 if let Some(v) = resp.version {
                     res.version = Some(v.name);
@@ -441,14 +1625,15 @@ async fn scan_server(ip: String, port: u16, check_auth: bool) -> ScanResult {
                     if let Some(sample) = p.sample {
                         res.players = Some(
                             sample.into_iter()
-                                .map(|p| Player { name: p.name, uuid: p.id })
+                                .map(|p| Player { name: p.name, uuid: Some(p.id) })
                                 .collect()
                         );
                     }
                 }
                 
                 if let Some(d) = resp.description {
-                    res.motd = Some(parse_motd(&d));
+                    res.motd = Some(parse_motd(&d, MotdRenderMode::PlainText));
+                    res.motd_ansi = Some(parse_motd(&d, MotdRenderMode::Ansi));
                 }
                 
                 res.favicon = resp.favicon;
@@ -462,34 +1647,124 @@ async fn scan_server(ip: String, port: u16, check_auth: bool) -> ScanResult {
                     }
                 }
             }
-            Err(e) => res.error = Some(e.to_string()),
+            Err(e) => {
+                // Only worth trying the legacy ping if we actually reached
+                // the host - a connect failure/timeout means it's dead or
+                // filtered, and the legacy ping would just time out again
+                if connected_stream.is_ok() {
+                    match legacy_server_list_ping(&ip, port).await {
+                        Ok(legacy) => {
+                            let mut plain = String::new();
+                            render_leaf_text(&legacy.motd, &ChatStyle::default(), MotdRenderMode::PlainText, &mut plain);
+                            let mut ansi = String::new();
+                            render_leaf_text(&legacy.motd, &ChatStyle::default(), MotdRenderMode::Ansi, &mut ansi);
+
+                            res.motd = Some(plain);
+                            res.motd_ansi = Some(ansi);
+                            res.version = legacy.version;
+                            res.online_players = legacy.online_players;
+                            res.max_players = legacy.max_players;
+                        }
+                        Err(_) => res.error = Some(e.to_string()),
+                    }
+                } else {
+                    res.error = Some(e.to_string());
+                }
+            }
+        }
+
+        // Second, optional stage: servers with enable-query on can give us
+        // a complete player list and plugin list the status ping can't
+        if check_query {
+            if let Ok(query_info) = query_server(&ip, port).await {
+                res.plugins = query_info.plugins;
+                if !query_info.players.is_empty() {
+                    res.players = Some(
+                        query_info.players.into_iter()
+                            .map(|name| Player { name, uuid: None })
+                            .collect()
+                    );
+                }
+                res.max_players = res.max_players.or(query_info.max_players);
+                res.online_players = res.online_players.or(query_info.num_players);
+            }
         }
+
         res
     }).await;
     
     scan_result.unwrap_or_else(|_| ScanResult {
         ip, port,
-        motd: None, version: None, protocol: None,
+        motd: None, motd_ansi: None, version: None, protocol: None,
         max_players: None, online_players: None, players: None,
-        favicon: None, auth_mode: None,
+        favicon: None, auth_mode: None, plugins: None,
         error: Some("Timeout".to_string()),
     })
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args = parse_args()?;
+
     let input = tokio::fs::read_to_string("input.txt").await?;
-    let lines: Vec<String> = input.lines()
+    let raw_lines: Vec<String> = input.lines()
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty() && !s.starts_with('#'))
         .collect();
-    
+
+    let (fixed_lines, fix_report) = fix_targets(&raw_lines);
+    if !fix_report.diagnostics.is_empty() {
+        println!("ğŸ”§ {} target(s) have config issues ({} auto-fixable, {} need manual attention)",
+            fix_report.diagnostics.len(), fix_report.applied, fix_report.manual);
+        for d in &fix_report.diagnostics {
+            println!("   line {}: '{}' - {} (suggested: '{}')", d.line_no, d.original, d.issue, d.suggested);
+        }
+        if args.fix {
+            if fix_report.applied > 0 {
+                tokio::fs::write("input.fixed.txt", fixed_lines.join("\n")).await?;
+                println!("ğŸ”§ Verified fixes written to input.fixed.txt ({} applied)", fix_report.applied);
+            } else {
+                println!("ğŸ”§ No fix both resolved the diagnostic and reverified clean - nothing written");
+            }
+        }
+        println!();
+    }
+
+    let lines = if args.fix { fixed_lines } else { raw_lines };
+
+    let mut resumed_tally = ScanTally::default();
+
+    let lines = if args.resume {
+        let (already_scanned, tally) = match tokio::fs::read_to_string(RESULTS_PATH).await {
+            Ok(existing) => parse_existing_results(&existing),
+            Err(_) => (HashSet::new(), ScanTally::default()),
+        };
+        resumed_tally = tally;
+        let before = lines.len();
+        let remaining: Vec<String> = lines
+            .into_iter()
+            .filter(|line| {
+                let (ip, port) = parse_target(line);
+                !already_scanned.contains(&format!("{}:{}", ip, port))
+            })
+            .collect();
+        if before > remaining.len() {
+            println!("🔁 Resuming: {} target(s) already in {}, {} remaining",
+                before - remaining.len(), RESULTS_PATH, remaining.len());
+            println!();
+        }
+        remaining
+    } else {
+        lines
+    };
+
     println!("ğŸ” Minecraft Server Scanner");
     println!("â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”");
     println!("ğŸ“Š Found {} servers to scan", lines.len());
     println!();
     
-    let check_auth = false;
+    let check_auth = args.check_auth;
+    let check_query = args.check_query;
     let max_concurrent = 500;
     
     let mp = MultiProgress::new();
@@ -503,65 +1778,50 @@ async fn main() -> Result<()> {
     
     let sem = Arc::new(Semaphore::new(max_concurrent));
     let mut tasks = Vec::new();
-    
+
+    let (tx, rx) = mpsc::channel::<ScanResult>(256);
+    let writer_handle = tokio::spawn(run_result_writer(rx, RESULTS_PATH.to_string(), args.resume, resumed_tally));
+
     for line in lines {
-        let (ip, port) = match line.split_once(':') {
-            Some((h, p)) => (h.to_string(), p.parse().unwrap_or(25565)),
-            None => (line.clone(), 25565)
-        };
-        
+        let (ip, port) = parse_target(&line);
+
         let s = sem.clone();
         let p = pb.clone();
         let st = status.clone();
-        
+        let tx = tx.clone();
+
         tasks.push(tokio::spawn(async move {
             let _permit = s.acquire().await.unwrap();
-            let r = scan_server(ip.clone(), port, check_auth).await;
+            let r = scan_server(ip.clone(), port, check_auth, check_query).await;
             p.inc(1);
-            
+
             if r.error.is_none() && r.version.is_some() {
-                st.set_message(format!("Success: {} | {} | Players: {}/{}",
+                st.set_message(format!("Success: {} | {} | Players: {}/{} | {}",
                     ip, r.version.as_ref().unwrap(),
-                    r.online_players.unwrap_or(0), r.max_players.unwrap_or(0)));
+                    r.online_players.unwrap_or(0), r.max_players.unwrap_or(0),
+                    r.motd_ansi.as_deref().unwrap_or("")));
             }
-            r
+            let _ = tx.send(r).await;
         }));
     }
-    
-    let mut results = Vec::new();
+    drop(tx);
+
     for t in tasks {
-        if let Ok(r) = t.await { results.push(r); }
+        let _ = t.await;
     }
-    
+
     pb.finish_with_message("Done!");
     status.finish_and_clear();
-    
-    let total = results.len();
-    let ok = results.iter().filter(|r| r.error.is_none()).count();
-    let online = results.iter().filter(|r| r.auth_mode == Some(1)).count();
-    let cracked = results.iter().filter(|r| r.auth_mode == Some(0)).count();
-    let wl = results.iter().filter(|r| r.auth_mode == Some(2)).count();
-    
     println!();
-    println!("â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”");
-    println!("ğŸ“ˆ Results:");
-    println!("   Total:      {}", total);
-    println!("   âœ“ Success:  {} ({:.1}%)", ok, (ok as f32 / total as f32) * 100.0);
-    println!("   âœ— Failed:   {} ({:.1}%)", total - ok, ((total - ok) as f32 / total as f32) * 100.0);
-    
-    if check_auth {
-        println!();
-        println!("ğŸ” Auth:");
-        println!("   ğŸŸ¢ Online:    {}", online);
-        println!("   ğŸŸ¡ Cracked:   {}", cracked);
-        println!("   ğŸ”´ Whitelist: {}", wl);
+
+    let tally = writer_handle.await??;
+    let report = ScanReport::new(tally, check_auth, &fix_report);
+
+    render_report(&report, &args, RESULTS_PATH)?;
+    println!("ğŸ’¾ Saved to: {}", RESULTS_PATH);
+    if let Some(path) = &args.output {
+        println!("ğŸ’¾ Report written to: {}", path);
     }
-    
-    tokio::fs::write("results.json", serde_json::to_string_pretty(&results)?).await?;
-    
-    println!();
-    println!("ğŸ’¾ Saved to: results.json");
-    println!("â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”â”");
-    
+
     Ok(())
 }